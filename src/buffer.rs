@@ -0,0 +1,159 @@
+//! A bounded cursor over mIRC's `data`/`parms` output buffers.
+//!
+//! The two `string_to_p*str` helpers in [`crate::helpers`] used to each
+//! re-encode, re-clamp, and re-null-terminate against a raw pointer +
+//! `maxlen`, while the real limit (`LOADINFO::m_bytes`) lives somewhere else
+//! entirely. This module factors that out into [`MircBuffer`], which those
+//! helpers now delegate to: a cursor over borrowed
+//! memory in the style of the standard library's `BorrowedBuf`/`ReadBuf`:
+//! it tracks a capacity and a filled length over memory it does not own,
+//! and dispatches to UTF-16 or the active narrow code page
+//! ([`crate::codepage::get_codepage`]) based on `m_unicode` so a
+//! caller just writes `buf.write_str(...)` and gets correct encoding,
+//! clamping, and null-termination for free.
+
+use crate::codepage::get_codepage;
+use crate::helpers::convert_wide_to_ansi_string;
+use crate::loadinfo::get_loadinfo;
+
+/// Returned by [`MircBuffer::write_str`] when the input didn't fully fit.
+///
+/// `written` and `dropped` are counted in code units of the buffer's
+/// encoding (`u16`s for UTF-16, bytes for the narrow code page), so a caller can tell
+/// exactly how much was lost instead of just learning that it was.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Truncated {
+    pub written: usize,
+    pub dropped: usize,
+}
+
+/// A write cursor over a `data`/`parms`-style buffer mIRC owns.
+pub struct MircBuffer {
+    ptr: *mut u8,
+    capacity: usize,
+    filled: usize,
+    unicode: bool,
+}
+
+impl MircBuffer {
+    /// Wrap `ptr`, using the `m_bytes` capacity and `m_unicode` flag from
+    /// the crate's global `LOADINFO`.
+    ///
+    /// `ptr` must point to at least `get_loadinfo().m_bytes` writable
+    /// bytes, as mIRC guarantees for its `data`/`parms` callback buffers.
+    pub fn new(ptr: *mut u8) -> Self {
+        let loadinfo = get_loadinfo();
+        MircBuffer::with_capacity(ptr, loadinfo.m_bytes as usize, loadinfo.m_unicode.as_bool())
+    }
+
+    /// Wrap `ptr` with an explicit byte capacity and encoding, for buffers
+    /// that aren't sized by `m_bytes` (e.g. a caller-supplied `maxlen`).
+    pub fn with_capacity(ptr: *mut u8, capacity: usize, unicode: bool) -> Self {
+        MircBuffer {
+            ptr,
+            capacity,
+            filled: 0,
+            unicode,
+        }
+    }
+
+    /// Bytes still available for content, leaving room for the trailing
+    /// null terminator -- a 2-byte `u16` terminator for a unicode buffer,
+    /// a single byte otherwise. Always measured in bytes, regardless of
+    /// encoding.
+    pub fn remaining(&self) -> usize {
+        if self.unicode {
+            (self.capacity / 2)
+                .saturating_sub(1)
+                .saturating_sub(self.filled / 2)
+                .saturating_mul(2)
+        } else {
+            self.capacity.saturating_sub(1).saturating_sub(self.filled)
+        }
+    }
+
+    /// Encode and append `s`, clamping to capacity and null-terminating.
+    ///
+    /// If `s` doesn't fully fit, as much of it as fits is still written
+    /// (and null-terminated), and `Err(Truncated)` reports how much was
+    /// dropped so the caller can detect the overflow.
+    pub fn write_str(&mut self, s: &str) -> Result<(), Truncated> {
+        if self.ptr.is_null() {
+            return Ok(());
+        }
+
+        if self.unicode {
+            self.write_utf16(s)
+        } else {
+            self.write_ansi(s)
+        }
+    }
+
+    /// Format and append `args`, as [`write_str`](Self::write_str).
+    pub fn append_fmt(&mut self, args: std::fmt::Arguments<'_>) -> Result<(), Truncated> {
+        self.write_str(&args.to_string())
+    }
+
+    fn write_utf16(&mut self, s: &str) -> Result<(), Truncated> {
+        let units: Vec<u16> = s.encode_utf16().collect();
+
+        let wide_ptr = self.ptr as *mut u16;
+        let wide_capacity = self.capacity / 2;
+        let wide_filled = self.filled / 2;
+        let room = wide_capacity.saturating_sub(1).saturating_sub(wide_filled);
+        let to_write = units.len().min(room);
+
+        // A capacity under 2 bytes leaves no room for even a lone null
+        // terminator; there is nothing safe to write at all.
+        if wide_capacity > 0 {
+            // SAFETY: `to_write` is clamped to leave room for the null
+            // terminator within `self.capacity` bytes of `self.ptr`, which
+            // the caller guaranteed is valid for that many bytes.
+            unsafe {
+                std::ptr::copy_nonoverlapping(units.as_ptr(), wide_ptr.add(wide_filled), to_write);
+                *wide_ptr.add(wide_filled + to_write) = 0;
+            }
+            self.filled += to_write * 2;
+        }
+
+        if to_write < units.len() {
+            Err(Truncated {
+                written: to_write,
+                dropped: units.len() - to_write,
+            })
+        } else {
+            Ok(())
+        }
+    }
+
+    fn write_ansi(&mut self, s: &str) -> Result<(), Truncated> {
+        let wide: Vec<u16> = s.encode_utf16().collect();
+        let ansi = convert_wide_to_ansi_string(wide.as_ptr(), wide.len(), get_codepage());
+        // `ansi` is always null-terminated by `convert_wide_to_ansi_string`;
+        // that terminator isn't content, so exclude it before clamping.
+        let content = &ansi[..ansi.len() - 1];
+
+        let room = self.remaining();
+        let to_write = content.len().min(room);
+
+        // A zero-byte capacity leaves no room for even a lone null
+        // terminator; there is nothing safe to write at all.
+        if self.capacity > 0 {
+            // SAFETY: see `write_utf16`; the same capacity invariant holds here.
+            unsafe {
+                std::ptr::copy_nonoverlapping(content.as_ptr(), self.ptr.add(self.filled), to_write);
+                *self.ptr.add(self.filled + to_write) = 0;
+            }
+            self.filled += to_write;
+        }
+
+        if to_write < content.len() {
+            Err(Truncated {
+                written: to_write,
+                dropped: content.len() - to_write,
+            })
+        } else {
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,115 @@
+//! Talk back to mIRC: run commands and evaluate identifiers from Rust.
+//!
+//! `LOADINFO` captures `m_hwnd` and `m_bytes`, but until now a DLL author
+//! had no way to actually use them to drive mIRC -- which is the whole
+//! point of a mIRC DLL. This module exposes that as safe [`exec`] and
+//! [`eval`] functions, built on mIRC's named file-mapping + `SendMessage`
+//! protocol: a command or identifier is copied into mIRC's shared `"mIRC"`
+//! file mapping in the build's encoding (UTF-16 when `m_unicode`, the active
+//! narrow code page otherwise, bounded by `m_bytes`), then `SendMessage(m_hwnd, WM_USER+200,
+//! ...)` asks mIRC to run it as a command, or `WM_USER+201` to evaluate it
+//! as an identifier and read the result back out of the same mapping. This
+//! mirrors the shared-mapping-plus-handle pattern the standard library uses
+//! for cross-process handles on Windows. The mapped view is created once
+//! per thread and cached, since `OpenFileMappingW`/`MapViewOfFile` are not
+//! cheap to redo on every call.
+
+use std::cell::RefCell;
+
+use windows::Win32::Foundation::{CloseHandle, HANDLE, HWND};
+use windows::Win32::System::Memory::{
+    FILE_MAP_WRITE, MEMORY_MAPPED_VIEW_ADDRESS, MapViewOfFile, OpenFileMappingW,
+};
+use windows::Win32::UI::WindowsAndMessaging::SendMessageW;
+use windows::core::w;
+
+use crate::buffer::MircBuffer;
+use crate::loadinfo::get_loadinfo;
+use crate::mircstr::{MircString, pwstr_to_mircstring};
+
+const WM_USER: u32 = 0x0400;
+const WM_MCOMMAND: u32 = WM_USER + 200;
+const WM_MEVALUATE: u32 = WM_USER + 201;
+
+struct MappedView {
+    handle: HANDLE,
+    view: MEMORY_MAPPED_VIEW_ADDRESS,
+}
+
+thread_local! {
+    static MAPPING: RefCell<Option<MappedView>> = const { RefCell::new(None) };
+}
+
+/// Run `cmd` as an mIRC command, as if typed into an editbox.
+///
+/// Does nothing if `m_hwnd` or the `"mIRC"` file mapping isn't available.
+pub fn exec(cmd: &str) {
+    send(cmd, WM_MCOMMAND);
+}
+
+/// Evaluate `identifier` (e.g. `$me` or `$chr(65)`) and return the result.
+///
+/// Returns an empty [`MircString`] if `m_hwnd` or the mapping isn't
+/// available.
+pub fn eval(identifier: &str) -> MircString {
+    let Some(ptr) = send(identifier, WM_MEVALUATE) else {
+        return MircString::new();
+    };
+
+    let loadinfo = get_loadinfo();
+    if loadinfo.m_unicode.as_bool() {
+        pwstr_to_mircstring(ptr as *const u16, loadinfo.m_bytes as usize)
+    } else {
+        crate::helpers::pstr_to_string(ptr, loadinfo.m_bytes as usize).into()
+    }
+}
+
+/// Write `text` into the cached mapping in the active encoding, then send
+/// `message` to mIRC. Returns the mapping's base pointer on success, so
+/// `eval` can read the result back out of it.
+fn send(text: &str, message: u32) -> Option<*const u8> {
+    let loadinfo = get_loadinfo();
+    if loadinfo.m_hwnd == HWND::default() {
+        return None;
+    }
+
+    with_mapping(|ptr| {
+        let mut buf = MircBuffer::new(ptr);
+        let _ = buf.write_str(text);
+
+        // SAFETY: `m_hwnd` was just checked non-null above; mIRC owns the
+        // window, and `SendMessageW` returns only after mIRC has processed
+        // the message, so the mapping holds the result once it returns.
+        unsafe {
+            let _ = SendMessageW(loadinfo.m_hwnd, message, None, None);
+        }
+
+        ptr as *const u8
+    })
+}
+
+/// Run `f` with the thread's cached view of mIRC's `"mIRC"` file mapping,
+/// opening and mapping it the first time this thread needs it.
+fn with_mapping<R>(f: impl FnOnce(*mut u8) -> R) -> Option<R> {
+    MAPPING.with(|cell| {
+        let mut slot = cell.borrow_mut();
+
+        if slot.is_none() {
+            // SAFETY: `OpenFileMappingW`/`MapViewOfFile` are infallible to
+            // call; failure is reported through their return values.
+            let mapped = unsafe {
+                let handle = OpenFileMappingW(FILE_MAP_WRITE.0, false, w!("mIRC")).ok()?;
+                let view = MapViewOfFile(handle, FILE_MAP_WRITE, 0, 0, 0);
+                if view.Value.is_null() {
+                    let _ = CloseHandle(handle);
+                    return None;
+                }
+                MappedView { handle, view }
+            };
+            *slot = Some(mapped);
+        }
+
+        let ptr = slot.as_ref().unwrap().view.Value as *mut u8;
+        Some(f(ptr))
+    })
+}
@@ -0,0 +1,35 @@
+//! The active code page for the narrow (`pstr_to_string`/`string_to_pstr`)
+//! conversion layer.
+//!
+//! `convert_ansi_to_wide_string`/`convert_wide_to_ansi_string` used to
+//! hardcode `CP_ACP`, so a non-Unicode mIRC build on a machine whose system
+//! code page differs from the channel's actual encoding would mojibake, and
+//! there was no way to exchange UTF-8 bytes on the narrow path at all. This
+//! module lets that be chosen once, at `LoadDll` time, and stored alongside
+//! `LOADINFO`. The call site is the `CODE_PAGE` constant in
+//! [`crate::ffi`], which `LoadDll` passes to [`set_codepage`] on startup --
+//! edit that constant to pick a different code page (e.g. [`CP_UTF8`]).
+
+use std::sync::OnceLock;
+
+use windows::Win32::Globalization::CP_ACP;
+
+/// UTF-8. Not exposed by the `windows` crate as a named constant, unlike
+/// `CP_ACP`, so it's defined here; see the
+/// [Microsoft code page identifiers](https://learn.microsoft.com/windows/win32/intl/code-page-identifiers).
+pub const CP_UTF8: u32 = 65001;
+
+static CODE_PAGE: OnceLock<u32> = OnceLock::new();
+
+/// The active code page for narrow-string conversions. Defaults to
+/// `CP_ACP` if [`set_codepage`] was never called.
+pub fn get_codepage() -> u32 {
+    *CODE_PAGE.get_or_init(|| CP_ACP)
+}
+
+/// Set the active code page. Intended to be called once, from `LoadDll`.
+/// Returns `Err(())` if it was already set, including implicitly to `CP_ACP`
+/// by an earlier [`get_codepage`] call.
+pub fn set_codepage(code_page: u32) -> Result<(), ()> {
+    CODE_PAGE.set(code_page).map_err(|_| ())
+}
@@ -1,8 +1,16 @@
+use windows::Win32::Globalization::CP_ACP;
 use windows::core::BOOL;
 
+use crate::codepage;
 use crate::loadinfo::{LOADINFO, set_loadinfo};
+use crate::logging;
 use crate::version::fix_m_version;
 
+/// The code page used for narrow (non-`m_unicode`) string conversions.
+/// Change this to `codepage::CP_UTF8` to exchange UTF-8 instead of the
+/// system ANSI code page on the narrow path.
+const CODE_PAGE: u32 = CP_ACP;
+
 #[allow(dead_code)]
 #[repr(i32)]
 enum UnloadReason {
@@ -46,6 +54,8 @@ extern "system" fn LoadDll(loadinfo: *mut LOADINFO) -> i32 {
     }
 
     set_loadinfo(loadinfo).expect("LOADINFO was already set");
+    codepage::set_codepage(CODE_PAGE).expect("code page was already set");
+    logging::init(log::LevelFilter::Info);
     0
 }
 
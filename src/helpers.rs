@@ -1,4 +1,7 @@
-use windows::{Win32::Globalization::{CP_ACP, MB_ERR_INVALID_CHARS, MultiByteToWideChar, WideCharToMultiByte}};
+use windows::{Win32::Globalization::{MB_ERR_INVALID_CHARS, MultiByteToWideChar, WideCharToMultiByte}};
+
+use crate::buffer::MircBuffer;
+use crate::codepage::get_codepage;
 
 pub fn pwstr_to_string(ptr: *const u16, maxlen: usize) -> String {
     if ptr.is_null() {
@@ -18,41 +21,21 @@ pub fn pstr_to_string(ptr: *const u8, maxlen: usize) -> String {
         return String::new();
     }
 
-    let wide_vec = convert_ansi_to_wide_string(ptr, maxlen);
+    let wide_vec = convert_ansi_to_wide_string(ptr, maxlen, get_codepage());
     pwstr_to_string(wide_vec.as_ptr(), wide_vec.len() * 2)
 }
 
 pub fn string_to_pwstr(s: &str, ptr: *const u16, maxlen: usize) {
-    if ptr.is_null() {
-        return;
-    }
-
-    let wide: Vec<u16> = s.encode_utf16().collect();
-    let len = wide.len().min(maxlen / 2 - 1); // Leave space for null terminator
-
-    unsafe {
-        std::ptr::copy_nonoverlapping(wide.as_ptr(), ptr as *mut u16, len);
-        *((ptr as *mut u16).add(len)) = 0; // null-terminate
-    }
+    let mut buf = MircBuffer::with_capacity(ptr as *mut u8, maxlen, true);
+    let _ = buf.write_str(s);
 }
 
 pub fn string_to_pstr(s: &str, ptr: *const u8, maxlen: usize) {
-    if ptr.is_null() {
-        return;
-    }
-
-    let wide: Vec<u16> = s.encode_utf16().collect(); // Null-terminated UTF-16
-    let ansi_vec = convert_wide_to_ansi_string(wide.as_ptr(), wide.len());
-
-    // We need to copy up to maxlen - 1 bytes to leave space for null terminator
-    let len = ansi_vec.len().min(maxlen - 1);
-    unsafe {
-        std::ptr::copy_nonoverlapping(ansi_vec.as_ptr(), ptr as *mut u8, len);
-        *((ptr as *mut u8).add(len)) = 0; // null-terminate
-    }
+    let mut buf = MircBuffer::with_capacity(ptr as *mut u8, maxlen, false);
+    let _ = buf.write_str(s);
 }
 
-/// Converts a null-terminated ANSI string (CP_ACP) to a new, null-terminated UTF-16 string.
+/// Converts a null-terminated ANSI string in `code_page` to a new, null-terminated UTF-16 string.
 ///
 /// This function uses the two-pass `MultiByteToWideChar` pattern to safely allocate
 /// the exact buffer size required for the new wide string.
@@ -61,6 +44,8 @@ pub fn string_to_pstr(s: &str, ptr: *const u8, maxlen: usize) {
 /// * `ansi_str_ptr`: A C-style pointer to a null-terminated ANSI string.
 /// * `max_ansi_bytes`: A safety limit. The function will not read past this many
 ///   bytes from `ansi_str_ptr`.
+/// * `code_page`: The code page `ansi_str_ptr` is encoded in, e.g. `CP_ACP` or
+///   [`crate::codepage::CP_UTF8`].
 ///
 /// # Returns
 /// A `Vec<u16>` containing the UTF-16 representation of the string, guaranteed
@@ -71,6 +56,7 @@ pub fn string_to_pstr(s: &str, ptr: *const u8, maxlen: usize) {
 fn convert_ansi_to_wide_string(
     ansi_str_ptr: *const u8,
     max_ansi_bytes: usize,
+    code_page: u32,
 ) -> Vec<u16> {
     // A null-terminated empty string is the safest default return.
     let null_terminated_empty_wide = || vec![0u16];
@@ -101,7 +87,7 @@ fn convert_ansi_to_wide_string(
     // PASS 1: Determine the required buffer size (in u16s)
     let required_wide_chars = unsafe {
         MultiByteToWideChar(
-            CP_ACP,
+            code_page,
             MB_ERR_INVALID_CHARS,
             ansi_content_slice,
             None, // lpWideCharStr = None
@@ -120,7 +106,7 @@ fn convert_ansi_to_wide_string(
     // PASS 2: Perform the actual conversion.
     let chars_written = unsafe {
         MultiByteToWideChar(
-            CP_ACP,
+            code_page,
             MB_ERR_INVALID_CHARS,
             ansi_content_slice,
             Some(&mut wide_buffer), // Pass the mutable slice
@@ -141,7 +127,7 @@ fn convert_ansi_to_wide_string(
     wide_buffer
 }
 
-/// Converts a null-terminated UTF-16 string to a new, null-terminated ANSI string (CP_ACP).
+/// Converts a null-terminated UTF-16 string to a new, null-terminated ANSI string in `code_page`.
 ///
 /// This function uses the two-pass `WideCharToMultiByte` pattern to safely allocate
 /// the exact buffer size required for the new ANSI string.
@@ -150,9 +136,13 @@ fn convert_ansi_to_wide_string(
 /// * `wide_str_ptr`: A C-style pointer to a null-terminated UTF-16 string.
 /// * `max_wide_chars`: A safety limit. The function will not read past this many
 ///   `u16` characters from `wide_str_ptr`.
+/// * `code_page`: The code page to encode into, e.g. `CP_ACP` or
+///   [`crate::codepage::CP_UTF8`]. `CP_UTF8` forbids the `lpDefaultChar`/
+///   `lpUsedDefaultChar` arguments that other code pages accept, which is why
+///   both are always passed as `None` here regardless of code page.
 ///
 /// # Returns
-/// A `Vec<u8>` containing the ANSI (CP_ACP) representation of the string, guaranteed
+/// A `Vec<u8>` containing the ANSI representation of the string, guaranteed
 /// to be terminated with a `0u8` null character.
 ///
 /// If `wide_str_ptr` is null, or if the conversion fails, this function
@@ -160,6 +150,7 @@ fn convert_ansi_to_wide_string(
 pub fn convert_wide_to_ansi_string(
     wide_str_ptr: *const u16,
     max_wide_chars: usize,
+    code_page: u32,
 ) -> Vec<u8> {
     // A null-terminated empty string is the safest default return.
     let null_terminated_empty_ansi = || vec![0u8];
@@ -190,7 +181,7 @@ pub fn convert_wide_to_ansi_string(
     // PASS 1: Determine the required buffer size (in u8s)
     let required_ansi_bytes = unsafe {
         WideCharToMultiByte(
-            CP_ACP,
+            code_page,
             0,
             wide_content_slice,
             None, // lpMultiByteStr = None
@@ -213,7 +204,7 @@ pub fn convert_wide_to_ansi_string(
     // It will not write its own null, as we passed an explicit length.
     let bytes_written = unsafe {
         WideCharToMultiByte(
-            CP_ACP,
+            code_page,
             0,
             wide_content_slice,
             Some(&mut ansi_buffer), // Pass the mutable slice
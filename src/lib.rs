@@ -1,12 +1,21 @@
+mod buffer;
+mod callback;
+mod codepage;
 mod ffi;
 mod helpers;
 mod loadinfo;
+mod logging;
+mod mircstr;
 mod threads;
 mod version;
 mod win_utils;
 
+pub use buffer::{MircBuffer, Truncated};
+pub use callback::{eval, exec};
+pub use codepage::{CP_UTF8, get_codepage, set_codepage};
 pub use helpers::*;
 pub use loadinfo::get_loadinfo;
+pub use mircstr::{MircString, pwstr_to_mircstring, wide_to_wtf8, wtf8_to_wide};
 pub use mirust_macros::mirust_fn;
 pub use threads::is_main_thread;
 
@@ -42,7 +42,7 @@ pub fn get_loadinfo() -> &'static LOADINFO {
             m_bytes: 900,
         };
 
-        println!(
+        log::warn!(
             "Initializing LOADINFO with default mIRC version v5.6 (packed = {})",
             default_version
         );
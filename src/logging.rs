@@ -0,0 +1,56 @@
+//! Route diagnostics into mIRC instead of a nonexistent console.
+//!
+//! A DLL loaded into the mIRC GUI process has no attached stdout, so the
+//! `println!` call previously in [`crate::loadinfo::get_loadinfo`] simply
+//! vanished. This module installs a [`log`] backend that instead formats
+//! each record as an `echo -s` command and runs it via [`crate::callback::exec`],
+//! so messages show up in mIRC's status window. If `m_hwnd` isn't available
+//! yet, it falls back to `OutputDebugStringW` so a debugger can still see it.
+
+use log::{LevelFilter, Log, Metadata, Record};
+use windows::Win32::Foundation::HWND;
+use windows::Win32::System::Diagnostics::Debug::OutputDebugStringW;
+use windows::core::PCWSTR;
+
+use crate::callback;
+use crate::loadinfo::get_loadinfo;
+
+static LOGGER: MircLogger = MircLogger;
+
+struct MircLogger;
+
+impl Log for MircLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        if get_loadinfo().m_hwnd == HWND::default() {
+            output_debug(&format!("[{}] {}", record.level(), record.args()));
+            return;
+        }
+
+        callback::exec(&format!("echo -s [{}] {}", record.level(), record.args()));
+    }
+
+    fn flush(&self) {}
+}
+
+fn output_debug(text: &str) {
+    let wide: Vec<u16> = text.encode_utf16().chain(std::iter::once(0)).collect();
+    // SAFETY: `wide` is a valid null-terminated UTF-16 buffer we own.
+    unsafe {
+        OutputDebugStringW(PCWSTR(wide.as_ptr()));
+    }
+}
+
+/// Install the mIRC logger as the global [`log`] backend and set the max
+/// level filter. Call this once from [`crate::ffi::LoadDll`].
+pub fn init(level: LevelFilter) {
+    log::set_max_level(level);
+    let _ = log::set_logger(&LOGGER);
+}
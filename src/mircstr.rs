@@ -0,0 +1,179 @@
+//! Lossless UTF-16 <-> byte-buffer conversion via WTF-8.
+//!
+//! mIRC text can contain ill-formed UTF-16: lone surrogates pasted in from
+//! elsewhere, or produced deliberately via `$chr(0xD800)`-style scripting.
+//! `String::from_utf16_lossy` replaces each of those with U+FFFD, which is
+//! fine for display but means the original bytes can never be written back
+//! out unchanged. `MircString` fixes that by generalizing UTF-8 to also
+//! encode the surrogate range U+D800-U+DFFF, the same WTF-8 scheme the
+//! standard library uses internally for `OsString` on Windows. Well-formed
+//! UTF-16 round-trips byte-identically to ordinary UTF-8; anything else
+//! still round-trips, just not through a real `String` until lossy display
+//! is explicitly requested via [`MircString::to_string_lossy`].
+
+/// A WTF-8 encoded byte buffer.
+///
+/// Like `String`, but permits encoded unpaired surrogates so that
+/// [`wtf8_to_wide`] can recover the exact original `u16` sequence that
+/// [`wide_to_wtf8`] produced it from.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct MircString {
+    bytes: Vec<u8>,
+}
+
+impl MircString {
+    /// An empty buffer.
+    pub fn new() -> Self {
+        MircString { bytes: Vec::new() }
+    }
+
+    /// The WTF-8 encoded bytes.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Decode to a `String`, replacing any unpaired surrogate (and thus any
+    /// byte sequence that isn't valid UTF-8) with U+FFFD.
+    pub fn to_string_lossy(&self) -> String {
+        String::from_utf8_lossy(&self.bytes).into_owned()
+    }
+}
+
+impl From<String> for MircString {
+    fn from(s: String) -> Self {
+        // Well-formed UTF-8 is always well-formed WTF-8.
+        MircString {
+            bytes: s.into_bytes(),
+        }
+    }
+}
+
+impl From<&str> for MircString {
+    fn from(s: &str) -> Self {
+        MircString {
+            bytes: s.as_bytes().to_vec(),
+        }
+    }
+}
+
+const SURROGATE_HIGH_START: u16 = 0xD800;
+const SURROGATE_HIGH_END: u16 = 0xDBFF;
+const SURROGATE_LOW_START: u16 = 0xDC00;
+const SURROGATE_LOW_END: u16 = 0xDFFF;
+
+/// Encode a `u16` code-unit slice (as handed back by mIRC's wide strings)
+/// into WTF-8, pairing up well-formed surrogate pairs into supplementary
+/// code points and passing through any unpaired surrogate as its own
+/// 3-byte sequence instead of replacing it.
+pub fn wide_to_wtf8(units: &[u16]) -> MircString {
+    let mut bytes = Vec::with_capacity(units.len());
+    let mut i = 0;
+
+    while i < units.len() {
+        let unit = units[i];
+
+        if (SURROGATE_HIGH_START..=SURROGATE_HIGH_END).contains(&unit)
+            && i + 1 < units.len()
+            && (SURROGATE_LOW_START..=SURROGATE_LOW_END).contains(&units[i + 1])
+        {
+            let high = unit as u32;
+            let low = units[i + 1] as u32;
+            let cp = 0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00);
+            push_utf8_4(&mut bytes, cp);
+            i += 2;
+            continue;
+        }
+
+        if (SURROGATE_HIGH_START..=SURROGATE_LOW_END).contains(&unit) {
+            // Unpaired surrogate: still a valid WTF-8 3-byte sequence, just
+            // not a valid Unicode scalar value.
+            push_utf8_3(&mut bytes, unit as u32);
+        } else if unit < 0x80 {
+            bytes.push(unit as u8);
+        } else if unit < 0x800 {
+            push_utf8_2(&mut bytes, unit as u32);
+        } else {
+            push_utf8_3(&mut bytes, unit as u32);
+        }
+
+        i += 1;
+    }
+
+    MircString { bytes }
+}
+
+/// Decode WTF-8 back into `u16` code units, reversing [`wide_to_wtf8`]:
+/// 4-byte sequences split back into a surrogate pair, and 3-byte sequences
+/// in the surrogate range decode directly to a lone surrogate.
+///
+/// `wtf8_to_wide(&wide_to_wtf8(x)) == x` for all `x: &[u16]`.
+pub fn wtf8_to_wide(s: &MircString) -> Vec<u16> {
+    let bytes = &s.bytes;
+    let mut units = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let b0 = bytes[i];
+
+        if b0 < 0x80 {
+            units.push(b0 as u16);
+            i += 1;
+        } else if b0 & 0xE0 == 0xC0 && i + 1 < bytes.len() {
+            let cp = ((b0 as u32 & 0x1F) << 6) | (bytes[i + 1] as u32 & 0x3F);
+            units.push(cp as u16);
+            i += 2;
+        } else if b0 & 0xF0 == 0xE0 && i + 2 < bytes.len() {
+            let cp = ((b0 as u32 & 0x0F) << 12)
+                | ((bytes[i + 1] as u32 & 0x3F) << 6)
+                | (bytes[i + 2] as u32 & 0x3F);
+            units.push(cp as u16);
+            i += 3;
+        } else if b0 & 0xF8 == 0xF0 && i + 3 < bytes.len() {
+            let cp = ((b0 as u32 & 0x07) << 18)
+                | ((bytes[i + 1] as u32 & 0x3F) << 12)
+                | ((bytes[i + 2] as u32 & 0x3F) << 6)
+                | (bytes[i + 3] as u32 & 0x3F);
+            let cp = cp - 0x10000;
+            units.push(0xD800 + ((cp >> 10) as u16));
+            units.push(0xDC00 + ((cp & 0x3FF) as u16));
+            i += 4;
+        } else {
+            // Malformed byte we can't interpret; skip it rather than panic.
+            i += 1;
+        }
+    }
+
+    units
+}
+
+fn push_utf8_2(bytes: &mut Vec<u8>, cp: u32) {
+    bytes.push(0xC0 | ((cp >> 6) as u8));
+    bytes.push(0x80 | ((cp & 0x3F) as u8));
+}
+
+fn push_utf8_3(bytes: &mut Vec<u8>, cp: u32) {
+    bytes.push(0xE0 | ((cp >> 12) as u8));
+    bytes.push(0x80 | (((cp >> 6) & 0x3F) as u8));
+    bytes.push(0x80 | ((cp & 0x3F) as u8));
+}
+
+fn push_utf8_4(bytes: &mut Vec<u8>, cp: u32) {
+    bytes.push(0xF0 | ((cp >> 18) as u8));
+    bytes.push(0x80 | (((cp >> 12) & 0x3F) as u8));
+    bytes.push(0x80 | (((cp >> 6) & 0x3F) as u8));
+    bytes.push(0x80 | ((cp & 0x3F) as u8));
+}
+
+/// Like [`crate::pwstr_to_string`], but lossless: any unpaired surrogate in
+/// the wide string survives as WTF-8 instead of becoming U+FFFD.
+pub fn pwstr_to_mircstring(ptr: *const u16, maxlen: usize) -> MircString {
+    if ptr.is_null() {
+        return MircString::new();
+    }
+
+    let maxlen = (maxlen & !1) / 2; // Convert byte length to number of u16 characters
+
+    let slice = unsafe { std::slice::from_raw_parts(ptr, maxlen) };
+    let len = slice.iter().position(|&c| c == 0).unwrap_or(maxlen);
+    wide_to_wtf8(&slice[..len])
+}